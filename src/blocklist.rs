@@ -0,0 +1,129 @@
+use postgres::Connection;
+use std::error::Error;
+use std::net::IpAddr;
+
+/// Tunables for a single blocklist refresh pass.
+pub struct Config {
+    pub window_mins: i64,
+    pub threshold: i64,
+    pub ban_mins: i64,
+}
+
+pub fn init_table(db_conn: &Connection) -> Result<(), Box<Error>> {
+    db_conn.execute(
+        "CREATE TABLE IF NOT EXISTS blocklist(
+            src_ip          INET PRIMARY KEY,
+            first_seen      TIMESTAMP WITH TIME ZONE NOT NULL,
+            last_seen       TIMESTAMP WITH TIME ZONE NOT NULL,
+            hit_count       BIGINT NOT NULL,
+            ban_expires_at  TIMESTAMP WITH TIME ZONE NOT NULL
+        )",
+        &[],
+    )?;
+    Ok(())
+}
+
+// Counts dropped/denied flows per src_ip over the trailing window and bans
+// (or re-bans) any source past the threshold, then ages out expired entries.
+pub fn refresh(db_conn: &Connection, cfg: &Config) -> Result<u64, Box<Error>> {
+    init_table(db_conn)?;
+
+    // FW_ACTION comes straight out of the `[TYPE-RULE-ACTION]` bracket (see
+    // parse_brackets), so it's always a single letter like "A"/"D"/"R" -
+    // never a full word such as "DROP".
+    let offenders = db_conn.query(
+        "SELECT src_ip, COUNT(*) AS hits
+         FROM entries
+         WHERE fw_action IN ('D', 'R')
+           AND logged_at > now() - ($1::bigint * interval '1 minute')
+         GROUP BY src_ip
+         HAVING COUNT(*) >= $2",
+        &[&cfg.window_mins, &cfg.threshold],
+    )?;
+
+    let mut banned = 0;
+    for row in &offenders {
+        let src_ip: IpAddr = row.get(0);
+        let hit_count: i64 = row.get(1);
+        // hit_count is the count over the whole trailing window, not "hits
+        // since the last refresh", so it must replace the stored value on
+        // every refresh rather than accumulate into it.
+        db_conn.execute(
+            "INSERT INTO blocklist(src_ip, first_seen, last_seen, hit_count, ban_expires_at)
+             VALUES($1, now(), now(), $2, now() + ($3::bigint * interval '1 minute'))
+             ON CONFLICT (src_ip) DO UPDATE SET
+                last_seen = now(),
+                hit_count = EXCLUDED.hit_count,
+                ban_expires_at = now() + ($3::bigint * interval '1 minute')",
+            &[&src_ip, &hit_count, &cfg.ban_mins],
+        )?;
+        banned += 1;
+    }
+
+    db_conn.execute("DELETE FROM blocklist WHERE ban_expires_at < now()", &[])?;
+
+    Ok(banned)
+}
+
+pub fn active(db_conn: &Connection) -> Result<Vec<IpAddr>, Box<Error>> {
+    let rows = db_conn.query(
+        "SELECT src_ip FROM blocklist WHERE ban_expires_at > now() ORDER BY hit_count DESC",
+        &[],
+    )?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Renders the active blocklist as a plain IP list or a ruleset an operator
+/// can feed straight into nft/iptables.
+pub fn render(ips: &[IpAddr], format: &str) -> String {
+    match format {
+        "nft" => ips
+            .iter()
+            .map(|ip| format!("add element inet firal blocked {{ {} }}", ip))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "iptables" => ips
+            .iter()
+            .map(|ip| format!("-A INPUT -s {} -j DROP", ip))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => ips
+            .iter()
+            .map(|ip| ip.to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_ips() -> Vec<IpAddr> {
+        vec!["192.168.1.8".parse().unwrap(), "10.0.0.1".parse().unwrap()]
+    }
+
+    #[test]
+    fn render_list_is_plain_ips() {
+        let expected = "192.168.1.8\n10.0.0.1";
+        assert_eq!(expected, render(&sample_ips(), "list"));
+    }
+
+    #[test]
+    fn render_nft_wraps_each_ip_in_a_set_element() {
+        let expected =
+            "add element inet firal blocked { 192.168.1.8 }\nadd element inet firal blocked { 10.0.0.1 }";
+        assert_eq!(expected, render(&sample_ips(), "nft"));
+    }
+
+    #[test]
+    fn render_iptables_emits_drop_rules() {
+        let expected = "-A INPUT -s 192.168.1.8 -j DROP\n-A INPUT -s 10.0.0.1 -j DROP";
+        assert_eq!(expected, render(&sample_ips(), "iptables"));
+    }
+
+    #[test]
+    fn render_empty_list_is_empty_string() {
+        assert_eq!("", render(&[], "list"));
+    }
+}
@@ -1,21 +1,84 @@
-use postgres::{Connection, TlsMode};
+use postgres::error::SqlState;
+use postgres::{Connection, Error as PgError, TlsMode};
+use r2d2_postgres::r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
 use std::env;
 use std::error::Error;
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub fn init() -> Result<Connection, Box<Error>> {
+/// Tunables for the reconnect backoff loop in `connect_with_backoff`.
+pub struct BackoffConfig {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub max_elapsed: Duration,
+}
+
+impl Default for BackoffConfig {
+    fn default() -> BackoffConfig {
+        BackoffConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 1.8,
+            max_elapsed: Duration::from_secs(300),
+        }
+    }
+}
+
+fn conn_string() -> String {
     let db_user = env::var("FIRAL_USER").expect("FIRAL_USER not set");
     let db_pass = env::var("FIRAL_PASS").expect("FIRAL_PASS not set");
     let db_host = env::var("FIRAL_HOST").expect("FIRAL_HOST not set");
     let db_db = env::var("FIRAL_DB").expect("FIRAL_DB not set");
-    let conn = Connection::connect(
-        format!(
-            "postgres://{}:{}@{}:5432/{}",
-            db_user, db_pass, db_host, db_db
-        ),
-        TlsMode::None,
-    )?;
+    format!(
+        "postgres://{}:{}@{}:5432/{}",
+        db_user, db_pass, db_host, db_db
+    )
+}
+
+// Authentication and missing-database errors are misconfiguration, not
+// transient outages, so we fail fast instead of retrying forever.
+fn is_permanent_code(code: Option<&SqlState>) -> bool {
+    match code {
+        Some(&SqlState::INVALID_PASSWORD)
+        | Some(&SqlState::INVALID_AUTHORIZATION_SPECIFICATION)
+        | Some(&SqlState::INVALID_CATALOG_NAME) => true,
+        _ => false,
+    }
+}
+
+fn is_permanent(err: &PgError) -> bool {
+    is_permanent_code(err.code())
+}
+
+fn connect_with_backoff(conn_str: &str, cfg: &BackoffConfig) -> Result<Connection, Box<Error>> {
+    let start = Instant::now();
+    let mut backoff = cfg.initial_backoff;
+    loop {
+        match Connection::connect(conn_str, TlsMode::None) {
+            Ok(conn) => return Ok(conn),
+            Err(e) => {
+                if is_permanent(&e) || start.elapsed() >= cfg.max_elapsed {
+                    return Err(Box::new(e));
+                }
+                eprintln!(
+                    "db connection failed ({}), retrying in {:?}",
+                    e, backoff
+                );
+                thread::sleep(backoff);
+                backoff = std::cmp::min(
+                    Duration::from_millis((backoff.as_millis() as f64 * cfg.multiplier) as u64),
+                    cfg.max_backoff,
+                );
+            },
+        }
+    }
+}
+
+pub fn init(cfg: &BackoffConfig) -> Result<Connection, Box<Error>> {
+    let conn = connect_with_backoff(&conn_string(), cfg)?;
 
-    // Opting not to use INET types for IP because the rust-postgres lib doesn't support it.
     conn.execute(
         "CREATE TABLE IF NOT EXISTS entries(
             id              BIGSERIAL PRIMARY KEY,
@@ -23,18 +86,74 @@ pub fn init() -> Result<Connection, Box<Error>> {
             dst_port        INT,
             packet_id       INT,
             packet_size     INT,
-            src_ip          VARCHAR(16) NOT NULL CHECK (src_ip <> ''),
-            dst_ip          VARCHAR(16) NOT NULL CHECK (dst_ip <> ''),
+            src_ip          INET NOT NULL,
+            dst_ip          INET NOT NULL,
             in_interface    VARCHAR(16) NOT NULL,
             out_interface   VARCHAR(16) NOT NULL,
             protocol        VARCHAR(16) NOT NULL,
             flow_type       VARCHAR(16) NOT NULL,
             rule_id         VARCHAR(32) NOT NULL,
+            fw_action       VARCHAR(2) NOT NULL DEFAULT '',
             logged_at       TIMESTAMP WITH TIME ZONE NOT NULL,
             UNIQUE(src_ip, protocol, packet_id, packet_size, logged_at)
         )",
         &[],
     )?;
 
+    // fw_action didn't exist in earlier schema versions; add it for
+    // databases created before the blocklist subsystem needed it.
+    conn.execute(
+        "ALTER TABLE entries ADD COLUMN IF NOT EXISTS fw_action VARCHAR(2) NOT NULL DEFAULT ''",
+        &[],
+    )?;
+
+    // `CREATE TABLE IF NOT EXISTS` is a no-op against a database that already
+    // has the old VARCHAR(16) src_ip/dst_ip columns, so migrate those in
+    // place. The check constraints from the old schema no longer typecheck
+    // once the column is INET, so they're dropped along with the conversion.
+    conn.execute(
+        "DO $$
+        BEGIN
+            IF (SELECT data_type FROM information_schema.columns
+                WHERE table_name = 'entries' AND column_name = 'src_ip') = 'character varying'
+            THEN
+                ALTER TABLE entries DROP CONSTRAINT IF EXISTS entries_src_ip_check;
+                ALTER TABLE entries DROP CONSTRAINT IF EXISTS entries_dst_ip_check;
+                ALTER TABLE entries ALTER COLUMN src_ip TYPE INET USING src_ip::inet;
+                ALTER TABLE entries ALTER COLUMN dst_ip TYPE INET USING dst_ip::inet;
+            END IF;
+        END $$;",
+        &[],
+    )?;
+
     Ok(conn)
 }
+
+/// Builds a pooled connection manager so concurrent TCP handlers can share
+/// a small set of long-lived connections instead of dialing per-line.
+pub fn init_pool(cfg: &BackoffConfig) -> Result<Pool<PostgresConnectionManager>, Box<Error>> {
+    // Run the one-shot schema setup over a throwaway connection first.
+    init(cfg)?;
+
+    let manager = PostgresConnectionManager::new(conn_string(), TlsMode::None)?;
+    let pool = Pool::new(manager)?;
+    Ok(pool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_permanent_code_flags_auth_and_missing_db_errors() {
+        assert!(is_permanent_code(Some(&SqlState::INVALID_PASSWORD)));
+        assert!(is_permanent_code(Some(&SqlState::INVALID_AUTHORIZATION_SPECIFICATION)));
+        assert!(is_permanent_code(Some(&SqlState::INVALID_CATALOG_NAME)));
+    }
+
+    #[test]
+    fn is_permanent_code_does_not_flag_transient_errors() {
+        assert!(!is_permanent_code(Some(&SqlState::CONNECTION_FAILURE)));
+        assert!(!is_permanent_code(None));
+    }
+}
@@ -1,12 +1,20 @@
 use chrono::DateTime;
+use firal::blocklist;
 use firal::db;
 use firal::model::Entry;
-use postgres::Connection;
+use postgres::error::SqlState;
+use postgres::{Connection, Error as PgError};
+use r2d2_postgres::r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::fs;
+use std::io::{BufRead, BufReader};
+use std::net::{IpAddr, TcpListener, TcpStream};
 use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 use structopt::StructOpt;
 
 #[derive(Debug, PartialEq)]
@@ -32,27 +40,194 @@ impl Error for OutOfBounds {
 #[structopt(name = "firal")]
 struct Opt {
     /// The path to the firewall logs
-    #[structopt(short = "f", long = "file", parse(from_os_str), required = true)]
-    file: PathBuf,
+    #[structopt(
+        short = "f",
+        long = "file",
+        parse(from_os_str),
+        conflicts_with = "listen"
+    )]
+    file: Option<PathBuf>,
+
+    /// Run as a TCP log sink instead of ingesting a file, e.g. 0.0.0.0:5514
+    #[structopt(short = "l", long = "listen", conflicts_with = "file")]
+    listen: Option<String>,
+
+    /// Analyze ingested entries and refresh the blocklist instead of ingesting
+    #[structopt(
+        long = "blocklist",
+        conflicts_with_all = &["file", "listen"]
+    )]
+    blocklist: bool,
+
+    /// Sliding window (minutes) over which dropped/denied flows are counted
+    #[structopt(long = "blocklist-window-mins", default_value = "60")]
+    blocklist_window_mins: i64,
+
+    /// Blocked-attempt count within the window that triggers a ban
+    #[structopt(long = "blocklist-threshold", default_value = "20")]
+    blocklist_threshold: i64,
+
+    /// How long a ban lasts before the entry ages out
+    #[structopt(long = "blocklist-ban-mins", default_value = "1440")]
+    blocklist_ban_mins: i64,
+
+    /// Output format for the active blocklist: list, nft, or iptables
+    #[structopt(long = "blocklist-format", default_value = "list")]
+    blocklist_format: String,
+
+    /// How long to keep retrying a failed db connection before giving up
+    #[structopt(long = "db-connect-timeout-secs", default_value = "300")]
+    db_connect_timeout_secs: u64,
+
+    /// Delay before the first db reconnect attempt
+    #[structopt(long = "db-initial-backoff-ms", default_value = "100")]
+    db_initial_backoff_ms: u64,
+
+    /// Upper bound on the delay between db reconnect attempts
+    #[structopt(long = "db-max-backoff-secs", default_value = "30")]
+    db_max_backoff_secs: u64,
+
+    /// Factor the db reconnect delay grows by after each failed attempt
+    #[structopt(long = "db-backoff-multiplier", default_value = "1.8")]
+    db_backoff_multiplier: f64,
 }
 
-fn insert_line(db_conn: &Connection, parsed: HashMap<&str, &str>) {
+fn backoff_config(opt: &Opt) -> db::BackoffConfig {
+    db::BackoffConfig {
+        initial_backoff: Duration::from_millis(opt.db_initial_backoff_ms),
+        max_backoff: Duration::from_secs(opt.db_max_backoff_secs),
+        multiplier: opt.db_backoff_multiplier,
+        max_elapsed: Duration::from_secs(opt.db_connect_timeout_secs),
+    }
+}
+
+const COPY_BATCH_SIZE: usize = 1000;
+
+const COPY_STMT: &str = "COPY entries(
+    src_ip,
+    src_port,
+    dst_ip,
+    dst_port,
+    packet_id,
+    packet_size,
+    protocol,
+    flow_type,
+    rule_id,
+    fw_action,
+    out_interface,
+    in_interface,
+    logged_at
+) FROM STDIN";
+
+// Unlogged landing zone for a batch that hit a UNIQUE_VIOLATION: same
+// columns as `entries` (minus the serial id and its constraint), so a
+// failed COPY can be retried here and merged with ON CONFLICT DO NOTHING
+// instead of falling back to one INSERT per row.
+const STAGING_TABLE_STMT: &str = "CREATE TEMP TABLE IF NOT EXISTS firal_copy_staging(
+    src_ip          INET NOT NULL,
+    src_port        INT,
+    dst_ip          INET NOT NULL,
+    dst_port        INT,
+    packet_id       INT,
+    packet_size     INT,
+    protocol        VARCHAR(16) NOT NULL,
+    flow_type       VARCHAR(16) NOT NULL,
+    rule_id         VARCHAR(32) NOT NULL,
+    fw_action       VARCHAR(2) NOT NULL DEFAULT '',
+    out_interface   VARCHAR(16),
+    in_interface    VARCHAR(16) NOT NULL,
+    logged_at       TIMESTAMP WITH TIME ZONE
+)";
+
+const STAGING_COPY_STMT: &str = "COPY firal_copy_staging(
+    src_ip,
+    src_port,
+    dst_ip,
+    dst_port,
+    packet_id,
+    packet_size,
+    protocol,
+    flow_type,
+    rule_id,
+    fw_action,
+    out_interface,
+    in_interface,
+    logged_at
+) FROM STDIN";
+
+const STAGING_MERGE_STMT: &str = "INSERT INTO entries(
+    src_ip, src_port, dst_ip, dst_port, packet_id, packet_size,
+    protocol, flow_type, rule_id, fw_action, out_interface, in_interface, logged_at
+) SELECT
+    src_ip, src_port, dst_ip, dst_port, packet_id, packet_size,
+    protocol, flow_type, rule_id, fw_action, out_interface, in_interface, logged_at
+FROM firal_copy_staging
+ON CONFLICT DO NOTHING";
+
+// A field that fails to parse is logged and left at its Entry::default()
+// value rather than panicking, so one corrupt field never aborts ingestion
+// of the rest of the file. Returns whether any field was malformed so the
+// caller can count it toward the ingest summary.
+fn build_entry(parsed: HashMap<&str, &str>) -> (Entry, bool) {
     let mut entry = Entry::new();
+    let mut malformed = false;
     for (k, v) in parsed {
         match k {
-            "ID" => entry.packet_id = v.parse::<i32>().unwrap(),
+            "ID" => match v.parse::<i32>() {
+                Ok(n) => entry.packet_id = n,
+                Err(e) => {
+                    eprintln!("malformed ID field ({}): {}", v, e);
+                    malformed = true;
+                },
+            },
             "IN" => entry.in_interface = v.to_string(),
             "OUT" => entry.out_interface = Some(v.to_string()),
-            "SRC" => entry.src_ip = v.to_string(),
-            "DST" => entry.dst_ip = v.to_string(),
-            "LEN" => entry.packet_size = v.parse::<i32>().unwrap(),
+            "SRC" => match v.parse::<IpAddr>() {
+                Ok(ip) => entry.src_ip = ip,
+                Err(e) => {
+                    eprintln!("malformed SRC field ({}): {}", v, e);
+                    malformed = true;
+                },
+            },
+            "DST" => match v.parse::<IpAddr>() {
+                Ok(ip) => entry.dst_ip = ip,
+                Err(e) => {
+                    eprintln!("malformed DST field ({}): {}", v, e);
+                    malformed = true;
+                },
+            },
+            "LEN" => match v.parse::<i32>() {
+                Ok(n) => entry.packet_size = n,
+                Err(e) => {
+                    eprintln!("malformed LEN field ({}): {}", v, e);
+                    malformed = true;
+                },
+            },
             "PROTO" => entry.protocol = v.to_string(),
-            "SPT" => entry.src_port = v.parse::<i32>().unwrap(),
-            "DPT" => entry.dst_port = v.parse::<i32>().unwrap(),
+            "SPT" => match v.parse::<i32>() {
+                Ok(n) => entry.src_port = n,
+                Err(e) => {
+                    eprintln!("malformed SPT field ({}): {}", v, e);
+                    malformed = true;
+                },
+            },
+            "DPT" => match v.parse::<i32>() {
+                Ok(n) => entry.dst_port = n,
+                Err(e) => {
+                    eprintln!("malformed DPT field ({}): {}", v, e);
+                    malformed = true;
+                },
+            },
             "RULE_ID" => entry.rule_id = v.to_string(),
             "FLOW_TYPE" => entry.flow_type = v.to_string(),
             "FW_ACTION" => entry.fw_action = v.to_string(),
-            "LOGGED_AT" => entry.logged_at = Some(DateTime::parse_from_rfc3339(v).unwrap()),
+            "LOGGED_AT" => match DateTime::parse_from_rfc3339(v) {
+                Ok(t) => entry.logged_at = Some(t),
+                Err(e) => {
+                    eprintln!("malformed LOGGED_AT field ({}): {}", v, e);
+                    malformed = true;
+                },
+            },
             _ => {
                 if cfg!(debug_assertions) {
                     eprintln!("ignored: {}", k);
@@ -60,7 +235,28 @@ fn insert_line(db_conn: &Connection, parsed: HashMap<&str, &str>) {
             },
         }
     }
+    (entry, malformed)
+}
+
+#[derive(Debug, PartialEq)]
+enum InsertOutcome {
+    Inserted,
+    Duplicate,
+    Failed,
+}
+
+// The UNIQUE(src_ip, protocol, packet_id, packet_size, logged_at) constraint
+// means re-ingesting an overlapping file is expected to hit duplicates; we
+// count and suppress those instead of logging them as failures.
+fn classify_insert_error(code: Option<&SqlState>) -> InsertOutcome {
+    if code == Some(&SqlState::UNIQUE_VIOLATION) {
+        InsertOutcome::Duplicate
+    } else {
+        InsertOutcome::Failed
+    }
+}
 
+fn insert_entry(db_conn: &Connection, entry: Entry) -> InsertOutcome {
     match db_conn.execute(
         "INSERT INTO entries(
             src_ip,
@@ -72,10 +268,11 @@ fn insert_line(db_conn: &Connection, parsed: HashMap<&str, &str>) {
             protocol,
             flow_type,
             rule_id,
+            fw_action,
             out_interface,
             in_interface,
             logged_at)
-        VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+        VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
         &[
             &entry.src_ip,
             &entry.src_port,
@@ -86,20 +283,135 @@ fn insert_line(db_conn: &Connection, parsed: HashMap<&str, &str>) {
             &entry.protocol,
             &entry.flow_type,
             &entry.rule_id,
+            &entry.fw_action,
             &entry.out_interface,
             &entry.in_interface,
             &entry.logged_at,
         ],
     ) {
-        Ok(v) => v,
+        Ok(_) => InsertOutcome::Inserted,
         Err(e) => {
-            eprintln!("failed to insert entry: {:?}: {}", entry, e);
-
-            // .execute returns a u64 to represent the number of rows updated
-            // the return type must match so we also return the same.
-            0
+            let outcome = classify_insert_error(e.code());
+            if outcome == InsertOutcome::Failed {
+                eprintln!("failed to insert entry: {:?}: {}", entry, e);
+            }
+            outcome
         },
+    }
+}
+
+fn insert_line(db_conn: &Connection, parsed: HashMap<&str, &str>) {
+    let (entry, _malformed) = build_entry(parsed);
+    insert_entry(db_conn, entry);
+}
+
+#[derive(Debug, Default)]
+struct IngestStats {
+    inserted: u64,
+    skipped_duplicate: u64,
+    failed: u64,
+    malformed: u64,
+}
+
+// Escapes a single COPY text-format field: backslash itself first, then the
+// characters the format treats specially.
+fn escape_copy_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn entry_to_copy_row(entry: &Entry) -> String {
+    let out_interface = match &entry.out_interface {
+        Some(v) => escape_copy_field(v),
+        None => "\\N".to_string(),
+    };
+    let logged_at = match entry.logged_at {
+        Some(v) => v.to_rfc3339(),
+        None => "\\N".to_string(),
     };
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        entry.src_ip,
+        entry.src_port,
+        entry.dst_ip,
+        entry.dst_port,
+        entry.packet_id,
+        entry.packet_size,
+        escape_copy_field(&entry.protocol),
+        escape_copy_field(&entry.flow_type),
+        escape_copy_field(&entry.rule_id),
+        escape_copy_field(&entry.fw_action),
+        out_interface,
+        escape_copy_field(&entry.in_interface),
+        logged_at,
+    )
+}
+
+// Retries a batch that hit a UNIQUE_VIOLATION by COPYing it into an
+// unconstrained staging table and merging with ON CONFLICT DO NOTHING, so
+// the common "re-ingested an overlapping file" case stays a couple of
+// statements instead of degrading to one INSERT per row.
+fn flush_batch_via_staging(
+    db_conn: &Connection,
+    batch: &[Entry],
+    data: &str,
+    stats: &mut IngestStats,
+) -> Result<(), PgError> {
+    db_conn.execute(STAGING_TABLE_STMT, &[])?;
+    db_conn.execute("TRUNCATE firal_copy_staging", &[])?;
+    db_conn.copy_in(STAGING_COPY_STMT, &[], &mut data.as_bytes())?;
+    let inserted = db_conn.execute(STAGING_MERGE_STMT, &[])?;
+    stats.inserted += inserted;
+    stats.skipped_duplicate += batch.len() as u64 - inserted;
+    db_conn.execute("TRUNCATE firal_copy_staging", &[])?;
+    Ok(())
+}
+
+fn fallback_to_per_row_insert(db_conn: &Connection, batch: &mut Vec<Entry>, stats: &mut IngestStats) {
+    for entry in batch.drain(..) {
+        match insert_entry(db_conn, entry) {
+            InsertOutcome::Inserted => stats.inserted += 1,
+            InsertOutcome::Duplicate => stats.skipped_duplicate += 1,
+            InsertOutcome::Failed => stats.failed += 1,
+        }
+    }
+}
+
+// Flushes a batch via COPY FROM STDIN. A single row that violates the
+// UNIQUE constraint fails the whole COPY; since re-ingesting an overlapping
+// file is the expected way that happens, that case is retried through the
+// staging table above rather than demoting the whole batch to per-row
+// inserts. Any other COPY error still falls back to inserting row by row.
+fn flush_batch(db_conn: &Connection, batch: &mut Vec<Entry>, stats: &mut IngestStats) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let mut data = String::new();
+    for entry in batch.iter() {
+        data.push_str(&entry_to_copy_row(entry));
+    }
+
+    match db_conn.copy_in(COPY_STMT, &[], &mut data.as_bytes()) {
+        Ok(rows) => stats.inserted += rows,
+        Err(e) if e.code() == Some(&SqlState::UNIQUE_VIOLATION) => {
+            if let Err(staging_err) = flush_batch_via_staging(db_conn, batch, &data, stats) {
+                eprintln!(
+                    "staging merge failed ({}), falling back to per-row insert",
+                    staging_err
+                );
+                fallback_to_per_row_insert(db_conn, batch, stats);
+            }
+        },
+        Err(e) => {
+            eprintln!("batch copy failed ({}), falling back to per-row insert", e);
+            fallback_to_per_row_insert(db_conn, batch, stats);
+        },
+    }
+    batch.clear();
 }
 
 fn parse_brackets(bracket: &str) -> Result<(&str, &str, &str, &str), OutOfBounds> {
@@ -161,31 +473,139 @@ fn parse_line(line: &str) -> Result<HashMap<&str, &str>, OutOfBounds> {
 
 fn ingest_file(file: PathBuf, db_conn: &Connection) -> Result<(), Box<dyn Error>> {
     let content = fs::read_to_string(file)?;
+    let mut batch = Vec::with_capacity(COPY_BATCH_SIZE);
+    let mut stats = IngestStats::default();
     for line in content.lines() {
         match parse_line(line) {
-            Ok(entry) => {
-                insert_line(db_conn, entry);
+            Ok(parsed) => {
+                let (entry, malformed) = build_entry(parsed);
+                if malformed {
+                    stats.malformed += 1;
+                }
+                batch.push(entry);
+                if batch.len() >= COPY_BATCH_SIZE {
+                    flush_batch(db_conn, &mut batch, &mut stats);
+                }
             },
             Err(err) => {
                 eprintln!("skipped line ({}), error: {}", line, err);
             },
         };
     }
+    flush_batch(db_conn, &mut batch, &mut stats);
+
+    println!(
+        "ingest complete: {} inserted, {} skipped (duplicate), {} failed, {} with malformed fields",
+        stats.inserted, stats.skipped_duplicate, stats.failed, stats.malformed
+    );
+    Ok(())
+}
+
+// Deliberately std::net + thread-per-connection rather than tokio: the rest
+// of the codebase (postgres::Connection, Connection::execute/query) is
+// blocking end to end, and there's no other async code or tokio dependency
+// anywhere in the tree. A thread per connection won't scale as well as async
+// tasks under very high connection counts, but it composes with the existing
+// blocking DB calls without pulling in an async runtime for one feature.
+// Revisit if connection volume ever makes thread-per-connection a bottleneck.
+//
+// Handles a single streaming connection, framing it line-by-line so log
+// lines split across TCP packets are reassembled before parsing.
+fn handle_connection(stream: TcpStream, pool: &Pool<PostgresConnectionManager>) {
+    let peer = stream
+        .peer_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    let reader = BufReader::new(stream);
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("connection {} read error: {}", peer, e);
+                break;
+            },
+        };
+        match parse_line(&line) {
+            Ok(parsed) => match pool.get() {
+                Ok(db_conn) => insert_line(&db_conn, parsed),
+                Err(e) => eprintln!("failed to get pooled db connection: {}", e),
+            },
+            Err(err) => {
+                eprintln!("skipped line ({}), error: {}", line, err);
+            },
+        };
+    }
+}
+
+fn listen(addr: &str, pool: Pool<PostgresConnectionManager>) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(addr)?;
+    println!("listening for firewall logs on {}", addr);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("failed to accept connection: {}", e);
+                continue;
+            },
+        };
+        let pool = pool.clone();
+        thread::spawn(move || handle_connection(stream, &pool));
+    }
+    Ok(())
+}
+
+fn run_blocklist(opt: &Opt, db_conn: &Connection) -> Result<(), Box<dyn Error>> {
+    let cfg = blocklist::Config {
+        window_mins: opt.blocklist_window_mins,
+        threshold: opt.blocklist_threshold,
+        ban_mins: opt.blocklist_ban_mins,
+    };
+    let banned = blocklist::refresh(db_conn, &cfg)?;
+    eprintln!("blocklist refresh: {} source(s) over threshold", banned);
+
+    let active = blocklist::active(db_conn)?;
+    println!("{}", blocklist::render(&active, &opt.blocklist_format));
     Ok(())
 }
 
 fn main() {
     let opt = Opt::from_args();
-    let db_conn = db::init().unwrap();
-    match ingest_file(opt.file, &db_conn) {
-        Ok(_) => {},
-        Err(e) => eprintln!("ingest failed with: {}", e),
-    };
+    let backoff_cfg = backoff_config(&opt);
+    if opt.blocklist {
+        let db_conn = db::init(&backoff_cfg).unwrap();
+        if let Err(e) = run_blocklist(&opt, &db_conn) {
+            eprintln!("blocklist refresh failed with: {}", e);
+        }
+        return;
+    }
+
+    let file = opt.file;
+    let listen_addr = opt.listen;
+    match (file, listen_addr) {
+        (Some(file), None) => {
+            let db_conn = db::init(&backoff_cfg).unwrap();
+            match ingest_file(file, &db_conn) {
+                Ok(_) => {},
+                Err(e) => eprintln!("ingest failed with: {}", e),
+            };
+        },
+        (None, Some(addr)) => {
+            let pool = db::init_pool(&backoff_cfg).unwrap();
+            if let Err(e) = listen(&addr, pool) {
+                eprintln!("listen failed with: {}", e);
+            }
+        },
+        _ => {
+            eprintln!("exactly one of --file, --listen, or --blocklist must be given");
+            std::process::exit(1);
+        },
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::net::Ipv4Addr;
 
     #[test]
     fn parse_brackets_happy_path() {
@@ -242,4 +662,98 @@ mod tests {
         let actual = parse_line(bad_line);
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn escape_copy_field_escapes_special_chars() {
+        let input = "back\\slash\ttab\nnewline\rreturn";
+        let expected = "back\\\\slash\\ttab\\nnewline\\rreturn";
+        assert_eq!(expected, escape_copy_field(input));
+    }
+
+    #[test]
+    fn escape_copy_field_passes_through_plain_text() {
+        assert_eq!("eth0", escape_copy_field("eth0"));
+    }
+
+    #[test]
+    fn entry_to_copy_row_encodes_nulls_for_optional_fields() {
+        let entry = Entry::new();
+        let row = entry_to_copy_row(&entry);
+        let fields: Vec<&str> = row.trim_end_matches('\n').split('\t').collect();
+        // out_interface
+        assert_eq!("\\N", fields[10]);
+        // logged_at
+        assert_eq!("\\N", fields[12]);
+    }
+
+    #[test]
+    fn entry_to_copy_row_encodes_present_optional_fields() {
+        let mut entry = Entry::new();
+        entry.out_interface = Some("eth1".to_string());
+        entry.logged_at = Some(DateTime::parse_from_rfc3339("2019-01-12T13:56:05-08:00").unwrap());
+        let row = entry_to_copy_row(&entry);
+        let fields: Vec<&str> = row.trim_end_matches('\n').split('\t').collect();
+        assert_eq!("eth1", fields[10]);
+        assert_eq!("2019-01-12T13:56:05-08:00", fields[12]);
+    }
+
+    #[test]
+    fn build_entry_happy_path_is_not_malformed() {
+        let mut parsed: HashMap<&str, &str> = HashMap::new();
+        parsed.insert("ID", "40048");
+        parsed.insert("SRC", "192.168.1.8");
+        parsed.insert("DST", "192.168.1.1");
+        parsed.insert("LEN", "52");
+        parsed.insert("SPT", "8080");
+        parsed.insert("DPT", "45117");
+        parsed.insert("LOGGED_AT", "2019-01-12T13:56:05-08:00");
+        let (entry, malformed) = build_entry(parsed);
+        assert!(!malformed);
+        assert_eq!(40048, entry.packet_id);
+        assert_eq!(8080, entry.src_port);
+    }
+
+    #[test]
+    fn build_entry_skips_bad_numeric_field_without_panicking() {
+        let mut parsed: HashMap<&str, &str> = HashMap::new();
+        parsed.insert("ID", "not-a-number");
+        let (entry, malformed) = build_entry(parsed);
+        assert!(malformed);
+        assert_eq!(0, entry.packet_id);
+    }
+
+    #[test]
+    fn build_entry_skips_bad_ip_field_without_panicking() {
+        let mut parsed: HashMap<&str, &str> = HashMap::new();
+        parsed.insert("SRC", "not-an-ip");
+        let (entry, malformed) = build_entry(parsed);
+        assert!(malformed);
+        assert_eq!(IpAddr::V4(Ipv4Addr::UNSPECIFIED), entry.src_ip);
+    }
+
+    #[test]
+    fn build_entry_skips_bad_timestamp_without_panicking() {
+        let mut parsed: HashMap<&str, &str> = HashMap::new();
+        parsed.insert("LOGGED_AT", "not-a-timestamp");
+        let (entry, malformed) = build_entry(parsed);
+        assert!(malformed);
+        assert!(entry.logged_at.is_none());
+    }
+
+    #[test]
+    fn classify_insert_error_dedupes_unique_violations() {
+        assert_eq!(
+            InsertOutcome::Duplicate,
+            classify_insert_error(Some(&SqlState::UNIQUE_VIOLATION))
+        );
+    }
+
+    #[test]
+    fn classify_insert_error_treats_other_errors_as_failed() {
+        assert_eq!(
+            InsertOutcome::Failed,
+            classify_insert_error(Some(&SqlState::CONNECTION_FAILURE))
+        );
+        assert_eq!(InsertOutcome::Failed, classify_insert_error(None));
+    }
 }
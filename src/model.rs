@@ -1,11 +1,12 @@
 use chrono;
+use std::net::{IpAddr, Ipv4Addr};
 
 #[derive(Debug)]
 pub struct Entry {
     pub id: i64,
-    pub src_ip: String,
+    pub src_ip: IpAddr,
     pub src_port: i32,
-    pub dst_ip: String,
+    pub dst_ip: IpAddr,
     pub dst_port: i32,
     pub packet_size: i32,
     pub packet_id: i32,
@@ -22,9 +23,9 @@ impl Default for Entry {
     fn default() -> Entry {
         Entry {
             id: 0,
-            src_ip: String::new(),
+            src_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
             src_port: 0,
-            dst_ip: String::new(),
+            dst_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
             dst_port: 0,
             packet_size: 0,
             packet_id: 0,